@@ -4,9 +4,530 @@
 use tauri::command;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
+use std::sync::{Mutex, OnceLock};
 use ssh2::Session;
+use ftp::FtpStream;
+use native_tls::TlsConnector;
 use std::net::TcpStream;
+use log::{error, info, warn, LevelFilter};
+
+// 单个日志文件超过该大小后滚动归档
+const LOG_ROTATE_SIZE: u64 = 5 * 1024 * 1024;
+
+// 写入 `get_app_dir()/logs` 下的持久化文件日志，按大小滚动
+struct FileLogger {
+    path: PathBuf,
+    file: Mutex<fs::File>,
+}
+
+impl FileLogger {
+    fn new(path: PathBuf) -> Result<Self, String> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("打开日志文件失败: {}", e))?;
+        Ok(FileLogger {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn rotate_if_needed(&self, file: &mut fs::File) {
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if size < LOG_ROTATE_SIZE {
+            return;
+        }
+
+        let archived = self.path.with_extension(format!(
+            "{}.old",
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        if fs::rename(&self.path, &archived).is_ok() {
+            if let Ok(new_file) = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+            {
+                *file = new_file;
+            }
+        }
+    }
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= LevelFilter::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "[{}] [{}] {}\n",
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.args()
+        );
+
+        if let Ok(mut file) = self.file.lock() {
+            self.rotate_if_needed(&mut file);
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// 日志文件路径固定为 `get_app_dir()/logs/rollwin.log`
+fn get_log_file_path() -> PathBuf {
+    Path::new(&get_app_dir()).join("logs").join("rollwin.log")
+}
+
+static LOGGER_INIT: OnceLock<()> = OnceLock::new();
+
+// 初始化持久化文件日志，供 main() 在启动时调用一次
+fn init_logging() -> Result<(), String> {
+    let log_path = get_log_file_path();
+    if let Some(dir) = log_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    }
+
+    let mut init_result = Ok(());
+    LOGGER_INIT.get_or_init(|| {
+        init_result = (|| -> Result<(), String> {
+            let logger = FileLogger::new(log_path)?;
+            log::set_boxed_logger(Box::new(logger))
+                .map_err(|e| format!("初始化日志系统失败: {}", e))?;
+            log::set_max_level(LevelFilter::Info);
+            Ok(())
+        })();
+    });
+    init_result
+}
+
+// 获取日志文件路径，供前端展示
+#[command]
+fn get_log_path() -> String {
+    get_log_file_path().to_string_lossy().to_string()
+}
+
+// 读取日志文件最近的若干行，供前端展示最新的传输记录
+#[command]
+fn read_recent_logs(lines: Option<usize>) -> Result<Vec<String>, String> {
+    let limit = lines.unwrap_or(200);
+    let log_path = get_log_file_path();
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&log_path)
+        .map_err(|e| format!("读取日志文件失败: {}", e))?;
+    let all_lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let start = all_lines.len().saturating_sub(limit);
+    Ok(all_lines[start..].to_vec())
+}
+
+// 远程目录项，屏蔽 SFTP/FTP 在目录遍历上的差异
+struct RemoteEntry {
+    name: String,
+    is_dir: bool,
+}
+
+// 解析 Unix 风格的 FTP LIST 输出，例如 "drwxr-xr-x 2 user group 4096 Jan 1 00:00 name"。
+// ftp crate 不提供 MLSD，只能退化为文本解析；跳过 `.`/`..`，避免 download_for_backup 递归自身。
+fn parse_unix_list_line(line: &str) -> Option<RemoteEntry> {
+    let is_dir = line.starts_with('d');
+    let is_symlink = line.starts_with('l');
+
+    // 前 8 个空白分隔字段是权限/链接数/属主/属组/大小/月/日/时间，文件名从第 9 个字段开始，
+    // 文件名本身可能包含空格，因此不能用 split_whitespace().last() 截取
+    let mut rest = line;
+    for _ in 0..8 {
+        let trimmed = rest.trim_start();
+        let idx = trimmed.find(char::is_whitespace)?;
+        rest = &trimmed[idx..];
+    }
+    let mut file_name = rest.trim_start();
+
+    // 软链接行形如 "name -> target"，只取链接名本身
+    if is_symlink {
+        file_name = file_name.split(" -> ").next().unwrap_or(file_name);
+    }
+    let file_name = file_name.trim();
+
+    if file_name.is_empty() || file_name == "." || file_name == ".." {
+        return None;
+    }
+
+    // 软链接可能指向文件也可能指向目录，LIST 行本身无法判断，保守地当作文件处理、不递归下载
+    Some(RemoteEntry {
+        name: file_name.to_string(),
+        is_dir: is_dir && !is_symlink,
+    })
+}
+
+// 远程传输后端统一接口，屏蔽 SFTP / FTP / FTPS 的实现差异
+trait RemoteTransfer {
+    fn mkdir(&mut self, path: &Path) -> Result<(), String>;
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<(), String>;
+    fn read_file(&mut self, path: &Path) -> Result<Vec<u8>, String>;
+    // 目录不存在时返回 Ok(None)，与旧版 download_for_backup 的容错行为保持一致
+    fn list_dir(&mut self, path: &Path) -> Result<Option<Vec<RemoteEntry>>, String>;
+    // 文件不存在（或无法确认）时返回 Ok(None)，供增量上传在跳过前核实远程文件确实存在
+    fn stat_size(&mut self, path: &Path) -> Result<Option<u64>, String>;
+}
+
+impl RemoteTransfer for ssh2::Sftp {
+    fn mkdir(&mut self, path: &Path) -> Result<(), String> {
+        let _ = ssh2::Sftp::mkdir(self, path, 0o755);
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        let mut remote_file = ssh2::Sftp::create(self, path)
+            .map_err(|e| format!("创建远程文件失败 {}: {}", path.display(), e))?;
+        remote_file
+            .write(contents)
+            .map_err(|e| format!("写入远程文件失败 {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<Vec<u8>, String> {
+        let mut remote_file = ssh2::Sftp::open(self, path)
+            .map_err(|e| format!("打开远程文件失败 {}: {}", path.display(), e))?;
+        let mut contents = Vec::new();
+        remote_file
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("读取远程文件失败 {}: {}", path.display(), e))?;
+        Ok(contents)
+    }
+
+    fn list_dir(&mut self, path: &Path) -> Result<Option<Vec<RemoteEntry>>, String> {
+        let remote_dir = match ssh2::Sftp::readdir(self, path) {
+            Ok(dir) => dir,
+            Err(_) => return Ok(None),
+        };
+
+        let mut entries = Vec::new();
+        for (entry_path, stat) in remote_dir {
+            let file_name = entry_path
+                .file_name()
+                .ok_or_else(|| "无效的文件名".to_string())?
+                .to_str()
+                .ok_or_else(|| "文件名编码错误".to_string())?
+                .to_string();
+            entries.push(RemoteEntry {
+                name: file_name,
+                is_dir: stat.is_dir(),
+            });
+        }
+        Ok(Some(entries))
+    }
+
+    fn stat_size(&mut self, path: &Path) -> Result<Option<u64>, String> {
+        match ssh2::Sftp::stat(self, path) {
+            Ok(stat) => Ok(stat.size),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl RemoteTransfer for FtpStream {
+    fn mkdir(&mut self, path: &Path) -> Result<(), String> {
+        let path_str = path.to_str().ok_or_else(|| "路径编码错误".to_string())?;
+        // FTP 服务器上目录已存在时会返回错误，与 SFTP 分支一样忽略即可
+        let _ = FtpStream::mkdir(self, path_str);
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        let path_str = path.to_str().ok_or_else(|| "路径编码错误".to_string())?;
+        let mut reader = Cursor::new(contents.to_vec());
+        FtpStream::put(self, path_str, &mut reader)
+            .map_err(|e| format!("写入远程文件失败 {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<Vec<u8>, String> {
+        let path_str = path.to_str().ok_or_else(|| "路径编码错误".to_string())?;
+        let cursor = FtpStream::simple_retr(self, path_str)
+            .map_err(|e| format!("读取远程文件失败 {}: {}", path.display(), e))?;
+        Ok(cursor.into_inner())
+    }
+
+    fn list_dir(&mut self, path: &Path) -> Result<Option<Vec<RemoteEntry>>, String> {
+        let path_str = path.to_str().ok_or_else(|| "路径编码错误".to_string())?;
+        let lines = match FtpStream::list(self, Some(path_str)) {
+            Ok(lines) => lines,
+            Err(_) => return Ok(None),
+        };
+
+        let mut entries = Vec::new();
+        for line in lines {
+            if let Some(entry) = parse_unix_list_line(&line) {
+                entries.push(entry);
+            }
+        }
+        Ok(Some(entries))
+    }
+
+    fn stat_size(&mut self, path: &Path) -> Result<Option<u64>, String> {
+        let path_str = path.to_str().ok_or_else(|| "路径编码错误".to_string())?;
+        match FtpStream::size(self, path_str) {
+            Ok(size) => Ok(size.map(|s| s as u64)),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+// 包装具体传输协议，供各命令按 `protocol` 参数统一调用
+// SFTP 分支额外保留 Session，以便钩子命令复用同一条连接执行远程命令
+enum Backend {
+    Sftp(ssh2::Session, ssh2::Sftp),
+    Ftp(FtpStream),
+}
+
+impl RemoteTransfer for Backend {
+    fn mkdir(&mut self, path: &Path) -> Result<(), String> {
+        match self {
+            Backend::Sftp(_, sftp) => sftp.mkdir(path),
+            Backend::Ftp(ftp) => ftp.mkdir(path),
+        }
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        match self {
+            Backend::Sftp(_, sftp) => sftp.write_file(path, contents),
+            Backend::Ftp(ftp) => ftp.write_file(path, contents),
+        }
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<Vec<u8>, String> {
+        match self {
+            Backend::Sftp(_, sftp) => sftp.read_file(path),
+            Backend::Ftp(ftp) => ftp.read_file(path),
+        }
+    }
+
+    fn list_dir(&mut self, path: &Path) -> Result<Option<Vec<RemoteEntry>>, String> {
+        match self {
+            Backend::Sftp(_, sftp) => sftp.list_dir(path),
+            Backend::Ftp(ftp) => ftp.list_dir(path),
+        }
+    }
+
+    fn stat_size(&mut self, path: &Path) -> Result<Option<u64>, String> {
+        match self {
+            Backend::Sftp(_, sftp) => sftp.stat_size(path),
+            Backend::Ftp(ftp) => ftp.stat_size(path),
+        }
+    }
+}
+
+// `protocol` 在引入多协议支持前并不存在，旧版前端调用不会带上它，缺省时保持原有的 SFTP 行为
+fn default_protocol() -> String {
+    "sftp".to_string()
+}
+
+// SSH 鉴权方式：密码、私钥文件或 ssh-agent，由调用方按需选择
+struct SshAuth {
+    private_key_path: Option<String>,
+    passphrase: Option<String>,
+    use_agent: bool,
+}
+
+// 根据鉴权方式对已握手的会话做用户认证，三种方式集中在此处选择
+fn authenticate(sess: &Session, username: &str, password: &str, auth: &SshAuth) -> Result<(), String> {
+    if auth.use_agent {
+        sess.userauth_agent(username)
+            .map_err(|e| format!("ssh-agent认证失败: {}", e))
+    } else if let Some(key_path) = &auth.private_key_path {
+        sess.userauth_pubkey_file(
+            username,
+            None,
+            Path::new(key_path),
+            auth.passphrase.as_deref(),
+        )
+        .map_err(|e| format!("公钥认证失败: {}", e))
+    } else {
+        sess.userauth_password(username, password)
+            .map_err(|e| format!("用户认证失败: {}", e))
+    }
+}
+
+// 根据 protocol 建立连接并完成鉴权，返回统一的 Backend
+fn connect(
+    protocol: &str,
+    host: &str,
+    username: &str,
+    password: &str,
+    auth: &SshAuth,
+) -> Result<Backend, String> {
+    info!("正在连接 {} ({})", host, protocol);
+    let result = match protocol {
+        "sftp" => {
+            let host_with_port = if !host.contains(":") {
+                format!("{}:22", host)
+            } else {
+                host.to_string()
+            };
+
+            let tcp = TcpStream::connect(&host_with_port)
+                .map_err(|e| format!("连接服务器失败: {}", e))?;
+
+            let mut sess = Session::new().map_err(|e| format!("创建会话失败: {}", e))?;
+            sess.set_tcp_stream(tcp);
+            sess.set_timeout(60000);
+            sess.handshake().map_err(|e| format!("SSH握手失败: {}", e))?;
+            info!("SSH握手成功，开始认证 {}", username);
+            authenticate(&sess, username, password, auth)?;
+            info!("用户认证成功: {}", username);
+
+            let sftp = sess.sftp().map_err(|e| format!("SFTP会话创建失败: {}", e))?;
+            Ok(Backend::Sftp(sess, sftp))
+        }
+        "ftp" | "ftps" => {
+            let host_with_port = if !host.contains(":") {
+                format!("{}:21", host)
+            } else {
+                host.to_string()
+            };
+
+            let mut ftp_stream = FtpStream::connect(&host_with_port)
+                .map_err(|e| format!("连接服务器失败: {}", e))?;
+
+            if protocol == "ftps" {
+                let ctx = TlsConnector::new().map_err(|e| format!("创建TLS上下文失败: {}", e))?;
+                let host_name = host_with_port
+                    .split(':')
+                    .next()
+                    .unwrap_or(&host_with_port);
+                ftp_stream = ftp_stream
+                    .into_secure(ctx, host_name)
+                    .map_err(|e| format!("升级FTPS失败: {}", e))?;
+            }
+
+            ftp_stream
+                .login(username, password)
+                .map_err(|e| format!("用户认证失败: {}", e))?;
+            info!("用户认证成功: {}", username);
+
+            Ok(Backend::Ftp(ftp_stream))
+        }
+        other => Err(format!("不支持的协议: {}", other)),
+    };
+
+    if let Err(e) = &result {
+        error!("连接失败: {}", e);
+    }
+    result
+}
+
+// 单条远程命令的执行结果，回传给前端展示
+#[derive(serde::Serialize, Clone)]
+struct CommandOutput {
+    command: String,
+    stdout: String,
+    stderr: String,
+    exit_status: i32,
+}
+
+// 在已建立的 SSH 会话上执行一条远程命令，捕获输出与退出码
+fn exec_remote_command(sess: &Session, window: &tauri::Window, cmd: &str) -> Result<i32, String> {
+    let mut channel = sess.channel_session()
+        .map_err(|e| format!("创建命令通道失败: {}", e))?;
+    channel.exec(cmd)
+        .map_err(|e| format!("执行命令失败 {}: {}", cmd, e))?;
+
+    // stdout 和 stderr 必须交替读取：命令在某一路输出过多时，对端缓冲区会被撑满并
+    // 等待我们读走，如果这时还卡在另一路的 read_to_string(EOF) 上就会彼此死锁
+    sess.set_blocking(false);
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let mut stdout_done = false;
+    let mut stderr_done = false;
+    let mut buf = [0u8; 4096];
+
+    while !stdout_done || !stderr_done {
+        if !stdout_done {
+            match channel.read(&mut buf) {
+                Ok(0) => stdout_done = true,
+                Ok(n) => stdout.push_str(&String::from_utf8_lossy(&buf[..n])),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    sess.set_blocking(true);
+                    return Err(format!("读取命令输出失败 {}: {}", cmd, e));
+                }
+            }
+        }
+        if !stderr_done {
+            match channel.stderr().read(&mut buf) {
+                Ok(0) => stderr_done = true,
+                Ok(n) => stderr.push_str(&String::from_utf8_lossy(&buf[..n])),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    sess.set_blocking(true);
+                    return Err(format!("读取命令错误输出失败 {}: {}", cmd, e));
+                }
+            }
+        }
+        if !stdout_done || !stderr_done {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+    sess.set_blocking(true);
+
+    channel.wait_close()
+        .map_err(|e| format!("等待命令结束失败 {}: {}", cmd, e))?;
+    let exit_status = channel.exit_status()
+        .map_err(|e| format!("获取命令退出码失败 {}: {}", cmd, e))?;
+
+    info!("远程命令 `{}` 退出码 {}", cmd, exit_status);
+    let _ = window.emit("command-output", CommandOutput {
+        command: cmd.to_string(),
+        stdout,
+        stderr,
+        exit_status,
+    });
+
+    Ok(exit_status)
+}
+
+// 依次执行一组部署钩子命令；pre 钩子遇到非零退出码时中止部署
+fn run_command_hooks(
+    backend: &Backend,
+    window: &tauri::Window,
+    commands: &[String],
+    abort_on_failure: bool,
+) -> Result<(), String> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let sess = match backend {
+        Backend::Sftp(sess, _) => sess,
+        Backend::Ftp(_) => return Err("当前协议不支持执行远程命令钩子".to_string()),
+    };
+
+    for cmd in commands {
+        let exit_status = exec_remote_command(sess, window, cmd)?;
+        if abort_on_failure && exit_status != 0 {
+            let err = format!("前置命令执行失败，退出码 {}: {}", exit_status, cmd);
+            error!("{}", err);
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
 
 // 获取备份目录
 #[command]
@@ -16,19 +537,90 @@ fn get_backup_dir(project_name: &str, env: &str) -> Result<String, String> {
         .join("backups")
         .join(project_name)
         .join(env);
-    
+
     // 确保备份目录存在
     fs::create_dir_all(&backup_dir)
         .map_err(|e| format!("创建备份目录失败: {}", e))?;
-    
+
     Ok(backup_dir.to_str()
         .ok_or_else(|| "路径转换失败".to_string())?
         .to_string())
 }
 
+// 备份快照信息，文件夹名即时间戳，天然按字典序排序
+#[derive(serde::Serialize)]
+struct BackupInfo {
+    id: String,
+    timestamp: String,
+}
+
+// 生成可排序的备份快照目录名，固定 9 位纳秒精度，避免同一秒内的两次备份 id 相同
+fn generate_backup_id() -> String {
+    chrono::Utc::now().format("%Y%m%dT%H%M%S%.9fZ").to_string()
+}
+
+// 在备份根目录下生成一个尚不存在的快照路径，即便出现 id 碰撞也不会覆盖已有快照
+fn reserve_backup_snapshot_path(backup_dir: &str) -> PathBuf {
+    loop {
+        let candidate = Path::new(backup_dir).join(generate_backup_id());
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+}
+
+// 列出某个备份根目录下的全部快照 id，按时间升序排列
+fn list_backup_ids(backup_dir: &str) -> Result<Vec<String>, String> {
+    let mut ids: Vec<String> = fs::read_dir(backup_dir)
+        .map_err(|e| format!("读取备份目录失败: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+// 列出指定项目/环境下的全部备份快照，最新的排在最前面
+#[command]
+fn list_backups(project_name: &str, env: &str) -> Result<Vec<BackupInfo>, String> {
+    let backup_dir = get_backup_dir(project_name, env)?;
+    let mut ids = list_backup_ids(&backup_dir)?;
+    ids.reverse();
+    Ok(ids
+        .into_iter()
+        .map(|id| BackupInfo {
+            timestamp: id.clone(),
+            id,
+        })
+        .collect())
+}
+
+// 清理超出保留数量的最旧备份快照
+fn prune_backups(backup_dir: &str, retention: usize) -> Result<(), String> {
+    if retention == 0 {
+        return Ok(());
+    }
+
+    let ids = list_backup_ids(backup_dir)?;
+    if ids.len() <= retention {
+        return Ok(());
+    }
+
+    let remove_count = ids.len() - retention;
+    for id in &ids[..remove_count] {
+        let snapshot_path = Path::new(backup_dir).join(id);
+        fs::remove_dir_all(&snapshot_path)
+            .map_err(|e| format!("清理过期备份失败 {}: {}", id, e))?;
+        info!("已清理过期备份: {}", snapshot_path.display());
+    }
+
+    Ok(())
+}
+
 // 从远程服务器下载文件到本地备份
 fn download_for_backup(
-    sftp: &ssh2::Sftp,
+    backend: &mut Backend,
     remote_path: &Path,
     backup_path: &Path,
 ) -> Result<(), String> {
@@ -37,38 +629,39 @@ fn download_for_backup(
         .map_err(|e| format!("创建备份目录失败: {}", e))?;
 
     // 读取远程目录内容
-    let remote_dir = match sftp.readdir(remote_path) {
-        Ok(dir) => dir,
-        Err(_) => {
+    let remote_dir = match backend.list_dir(remote_path)? {
+        Some(dir) => dir,
+        None => {
             // 如果目录不存在，直接返回
             return Ok(());
         }
     };
 
-    // 遍历并下载每个文件
-    for (path, stat) in remote_dir {
-        let file_name = path.file_name()
-            .ok_or_else(|| "无效的文件名".to_string())?
-            .to_str()
-            .ok_or_else(|| "文件名编码错误".to_string())?;
-        
-        let remote_file_path = remote_path.join(file_name);
-        let backup_file_path = backup_path.join(file_name);
+    // 遍历并下载每个文件，跳过 `.`/`..`（某些 SFTP/FTP 服务器的目录列表会包含它们），
+    // 否则会将 remote_path 自身当作子目录递归下去
+    for entry in remote_dir {
+        if entry.name == "." || entry.name == ".." {
+            continue;
+        }
+        let remote_file_path = remote_path.join(&entry.name);
+        let backup_file_path = backup_path.join(&entry.name);
 
-        if stat.is_dir() {
+        if entry.is_dir {
             // 如果是目录递归下载
-            download_for_backup(sftp, &remote_file_path, &backup_file_path)?;
+            download_for_backup(backend, &remote_file_path, &backup_file_path)?;
         } else {
             // 如果是文件，直接下载
-            let mut remote_file = sftp.open(&remote_file_path)
-                .map_err(|e| format!("打开远程文件失败 {}: {}", file_name, e))?;
-            
-            let mut contents = Vec::new();
-            remote_file.read_to_end(&mut contents)
-                .map_err(|e| format!("读取远程文件失败 {}: {}", file_name, e))?;
+            let contents = match backend.read_file(&remote_file_path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    error!("下载远程文件失败 {}: {}", entry.name, e);
+                    return Err(format!("下载远程文件失败 {}: {}", entry.name, e));
+                }
+            };
 
             fs::write(&backup_file_path, contents)
-                .map_err(|e| format!("写入备份文件失败 {}: {}", file_name, e))?;
+                .map_err(|e| format!("写入备份文件失败 {}: {}", entry.name, e))?;
+            info!("已备份文件: {}", remote_file_path.display());
         }
     }
 
@@ -81,6 +674,74 @@ struct UploadProgress {
     current: usize,
     total: usize,
     percentage: f32,
+    transferred: usize,
+    skipped: usize,
+}
+
+// 增量部署清单：远程路径 -> 本地文件内容的 SHA-256
+type UploadManifest = std::collections::HashMap<String, String>;
+
+// 计算本地文件内容的 SHA-256，用于增量部署比对
+fn compute_file_hash(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("打开本地文件失败 {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("计算文件哈希失败 {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// 清单按 host+remote_path 再分一层目录，避免把同一个 project/env 部署到不同主机或
+// 不同远程根目录时，错误复用彼此的增量记录
+fn manifest_scope_key(host: &str, remote_path: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(host.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(remote_path.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// 增量清单固定存放在 `get_app_dir()/manifests/<project>/<env>/<host+remote_path 的哈希>/.rollwin-manifest.json`
+fn get_manifest_path(project_name: &str, env: &str, host: &str, remote_path: &str) -> PathBuf {
+    Path::new(&get_app_dir())
+        .join("manifests")
+        .join(project_name)
+        .join(env)
+        .join(manifest_scope_key(host, remote_path))
+        .join(".rollwin-manifest.json")
+}
+
+// 加载增量清单，清单不存在时视为空
+fn load_manifest(project_name: &str, env: &str, host: &str, remote_path: &str) -> Result<UploadManifest, String> {
+    let manifest_path = get_manifest_path(project_name, env, host, remote_path);
+    if !manifest_path.exists() {
+        return Ok(UploadManifest::new());
+    }
+
+    let content = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("读取增量清单失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析增量清单失败: {}", e))
+}
+
+// 保存增量清单
+fn save_manifest(
+    project_name: &str,
+    env: &str,
+    host: &str,
+    remote_path: &str,
+    manifest: &UploadManifest,
+) -> Result<(), String> {
+    let manifest_path = get_manifest_path(project_name, env, host, remote_path);
+    if let Some(dir) = manifest_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建增量清单目录失败: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("序列化增量清单失败: {}", e))?;
+    fs::write(&manifest_path, content).map_err(|e| format!("写入增量清单失败: {}", e))
 }
 
 // 添加一个函数来计算目录中的文件总数
@@ -103,14 +764,19 @@ fn count_files(path: &Path) -> Result<usize, String> {
 
 // 修改 upload_dir 函数，添加进度回调
 fn upload_dir(
-    sftp: &ssh2::Sftp, 
-    local_path: &Path, 
+    backend: &mut Backend,
+    local_path: &Path,
     remote_path: &Path,
     window: tauri::Window,
     total_files: usize,
     uploaded_files: &mut usize,
+    skipped_files: &mut usize,
+    manifest: &mut UploadManifest,
+    touched_keys: &mut std::collections::HashSet<String>,
+    incremental: bool,
 ) -> Result<(), String> {
-    let _ = sftp.mkdir(remote_path, 0o755);
+    backend.mkdir(remote_path)?;
+    info!("已创建远程目录: {}", remote_path.display());
 
     for entry in fs::read_dir(local_path)
         .map_err(|e| format!("读取本地目录失败: {}", e))? {
@@ -123,37 +789,89 @@ fn upload_dir(
         let remote_path = remote_path.join(file_name);
 
         if local_path.is_dir() {
-            let _ = sftp.mkdir(&remote_path, 0o755);
-            upload_dir(sftp, &local_path, &remote_path, window.clone(), total_files, uploaded_files)?;
+            backend.mkdir(&remote_path)?;
+            upload_dir(
+                backend,
+                &local_path,
+                &remote_path,
+                window.clone(),
+                total_files,
+                uploaded_files,
+                skipped_files,
+                manifest,
+                touched_keys,
+                incremental,
+            )?;
         } else {
+            let manifest_key = remote_path.to_string_lossy().to_string();
+            let hash = if incremental {
+                Some(compute_file_hash(&local_path)?)
+            } else {
+                None
+            };
+
+            if incremental {
+                touched_keys.insert(manifest_key.clone());
+            }
+
+            if let Some(hash) = &hash {
+                if manifest.get(&manifest_key) == Some(hash) {
+                    // 清单命中不代表远程文件真的还在：服务器可能已被重置，或清单是从另一台
+                    // 主机继承来的，因此跳过前必须向传输后端核实远程文件确实存在且大小一致
+                    let local_size = fs::metadata(&local_path)
+                        .map_err(|e| format!("读取本地文件信息失败 {}: {}", local_path.display(), e))?
+                        .len();
+                    if backend.stat_size(&remote_path)? == Some(local_size) {
+                        *skipped_files += 1;
+                        info!("跳过未变更文件: {}", remote_path.display());
+                        emit_upload_progress(&window, total_files, *uploaded_files, *skipped_files);
+                        continue;
+                    }
+                    warn!(
+                        "清单记录与远程文件不符，重新上传: {}",
+                        remote_path.display()
+                    );
+                }
+            }
+
             let mut file = fs::File::open(&local_path)
                 .map_err(|e| format!("打开本地文件失败 {}: {}", local_path.display(), e))?;
             let mut contents = Vec::new();
             file.read_to_end(&mut contents)
                 .map_err(|e| format!("读取文件内容失败 {}: {}", local_path.display(), e))?;
 
-            let mut remote_file = sftp.create(&remote_path)
-                .map_err(|e| format!("创建远程文件失败 {}: {}", remote_path.display(), e))?;
-            remote_file.write(&contents)
-                .map_err(|e| format!("写入远程文件失败 {}: {}", remote_path.display(), e))?;
+            if let Err(e) = backend.write_file(&remote_path, &contents) {
+                error!("上传文件失败 {}: {}", remote_path.display(), e);
+                return Err(e);
+            }
+            info!("已上传文件: {}", remote_path.display());
+
+            if let Some(hash) = hash {
+                manifest.insert(manifest_key, hash);
+            }
 
             *uploaded_files += 1;
-            
-            // 发送进度更新事件
-            let percentage = (*uploaded_files as f32 / total_files as f32) * 100.0;
-            let progress = UploadProgress {
-                current: *uploaded_files,
-                total: total_files,
-                percentage,
-            };
-            
-            let _ = window.emit("upload-progress", progress);
+            emit_upload_progress(&window, total_files, *uploaded_files, *skipped_files);
         }
     }
 
     Ok(())
 }
 
+// 发送上传进度事件，区分已传输与已跳过的文件数
+fn emit_upload_progress(window: &tauri::Window, total_files: usize, uploaded_files: usize, skipped_files: usize) {
+    let processed = uploaded_files + skipped_files;
+    let percentage = (processed as f32 / total_files as f32) * 100.0;
+    let progress = UploadProgress {
+        current: processed,
+        total: total_files,
+        percentage,
+        transferred: uploaded_files,
+        skipped: skipped_files,
+    };
+    let _ = window.emit("upload-progress", progress);
+}
+
 #[command]
 async fn deploy_project(
     window: tauri::Window,
@@ -164,40 +882,38 @@ async fn deploy_project(
     username: String,
     password: String,
     remote_path: String,
+    protocol: Option<String>,
+    private_key_path: Option<String>,
+    passphrase: Option<String>,
+    use_agent: Option<bool>,
+    incremental: Option<bool>,
+    pre_commands: Option<Vec<String>>,
+    post_commands: Option<Vec<String>>,
 ) -> Result<(), String> {
-    // 连接服务器
-    let host_with_port = if !host.contains(":") {
-        format!("{}:22", host)
+    let protocol = protocol.unwrap_or_else(default_protocol);
+    let auth = SshAuth {
+        private_key_path,
+        passphrase,
+        use_agent: use_agent.unwrap_or(false),
+    };
+    let incremental = incremental.unwrap_or(false);
+    let pre_commands = pre_commands.unwrap_or_default();
+    let post_commands = post_commands.unwrap_or_default();
+    let mut manifest = if incremental {
+        load_manifest(&project_name, &env, &host, &remote_path)?
     } else {
-        host.clone()
+        UploadManifest::new()
     };
 
     // 添加重试逻辑
     let mut retries = 3;
     let mut last_error = None;
-    
-    while retries > 0 {
-        match TcpStream::connect(&host_with_port) {
-            Ok(tcp) => {
-                let mut sess = Session::new()
-                    .map_err(|e| format!("创建会话失败: {}", e))?;
-                
-                sess.set_tcp_stream(tcp);
-                sess.set_timeout(60000);  // 60秒
-                
-                // 添加更详细的错误处理
-                if let Err(e) = sess.handshake() {
-                    return Err(format!("SSH握手失败: {}", e));
-                }
 
-                if let Err(e) = sess.userauth_password(&username, &password) {
-                    return Err(format!("用户认证失败: {}", e));
-                }
-
-                let sftp = match sess.sftp() {
-                    Ok(sftp) => sftp,
-                    Err(e) => return Err(format!("SFTP会话创建失败: {}", e))
-                };
+    while retries > 0 {
+        match connect(&protocol, &host, &username, &password, &auth) {
+            Ok(mut backend) => {
+                // 部署前先执行前置命令，失败则中止本次部署
+                run_command_hooks(&backend, &window, &pre_commands, true)?;
 
                 // 计算总文件数
                 let local_path = Path::new(&path);
@@ -211,17 +927,40 @@ async fn deploy_project(
                 }
 
                 let mut uploaded_files = 0;
+                let mut skipped_files = 0;
+                let mut touched_keys = std::collections::HashSet::new();
 
                 // 上传文件
-                let remote_path = Path::new(&remote_path);
-                upload_dir(&sftp, local_path, remote_path, window, total_files, &mut uploaded_files)
-                    .map_err(|e| format!("上传文件失败: {}", e))?;
+                let remote_dir_path = Path::new(&remote_path);
+                upload_dir(
+                    &mut backend,
+                    local_path,
+                    remote_dir_path,
+                    window.clone(),
+                    total_files,
+                    &mut uploaded_files,
+                    &mut skipped_files,
+                    &mut manifest,
+                    &mut touched_keys,
+                    incremental,
+                )
+                .map_err(|e| format!("上传文件失败: {}", e))?;
+
+                if incremental {
+                    // 清单只记录这次部署实际遍历到的本地文件，本地已删除的文件不再保留陈旧记录
+                    manifest.retain(|key, _| touched_keys.contains(key));
+                    save_manifest(&project_name, &env, &host, &remote_path, &manifest)?;
+                }
+
+                // 文件上传完成后执行后置命令
+                run_command_hooks(&backend, &window, &post_commands, false)?;
 
                 return Ok(());
             }
             Err(e) => {
-                last_error = Some(e);
                 retries -= 1;
+                warn!("连接 {} 失败，剩余重试次数 {}: {}", host, retries, e);
+                last_error = Some(e);
                 if retries > 0 {
                     std::thread::sleep(std::time::Duration::from_secs(2));
                 }
@@ -229,7 +968,9 @@ async fn deploy_project(
         }
     }
 
-    Err(format!("连接服务器失败，已重试3次: {}", last_error.unwrap()))
+    let err = format!("连接服务器失败，已重试3次: {}", last_error.unwrap());
+    error!("{}", err);
+    Err(err)
 }
 
 #[command]
@@ -242,39 +983,42 @@ async fn rollback_project(
     username: String,
     password: String,
     remote_path: String,
+    protocol: Option<String>,
+    private_key_path: Option<String>,
+    passphrase: Option<String>,
+    use_agent: Option<bool>,
+    backup_id: Option<String>,
+    pre_commands: Option<Vec<String>>,
+    post_commands: Option<Vec<String>>,
 ) -> Result<(), String> {
-    // 连接服务器
-    let host_with_port = if !host.contains(":") {
-        format!("{}:22", host)
-    } else {
-        host
+    let protocol = protocol.unwrap_or_else(default_protocol);
+    let auth = SshAuth {
+        private_key_path,
+        passphrase,
+        use_agent: use_agent.unwrap_or(false),
     };
+    let pre_commands = pre_commands.unwrap_or_default();
+    let post_commands = post_commands.unwrap_or_default();
+    let mut backend = connect(&protocol, &host, &username, &password, &auth)?;
 
-    let tcp = TcpStream::connect(&host_with_port)
-        .map_err(|e| format!("连接服务器失败: {}", e))?;
-    
-    let mut sess = Session::new()
-        .map_err(|e| format!("创建会话失败: {}", e))?;
-    
-    sess.set_tcp_stream(tcp);
-    sess.set_timeout(60000);  // 60秒
-    sess.handshake()
-        .map_err(|e| format!("握手失败: {}", e))?;
+    // 回滚前先执行前置命令，失败则中止本次回滚
+    run_command_hooks(&backend, &window, &pre_commands, true)?;
 
-    sess.userauth_password(&username, &password)
-        .map_err(|e| format!("认证失败: {}", e))?;
-
-    let sftp = sess.sftp()
-        .map_err(|e| format!("创建SFTP会话失败: {}", e))?;
-
-    // 获取最新的备份
+    // 选择要恢复的备份快照，未指定时取最新的一份
     let backup_dir = get_backup_dir(&project_name, &env)?;
-    let backup_path = PathBuf::from(backup_dir);
+    let backup_id = match backup_id {
+        Some(id) => id,
+        None => list_backup_ids(&backup_dir)?
+            .pop()
+            .ok_or_else(|| "没有找到可用的备份".to_string())?,
+    };
+    let backup_path = Path::new(&backup_dir).join(&backup_id);
 
     // 检查备份是否存在
     if !backup_path.exists() {
-        return Err("没有找到可用的备份".to_string());
+        return Err(format!("备份快照不存在: {}", backup_id));
     }
+    info!("开始从备份快照 {} 回滚", backup_id);
 
     // 上传 version.json
     let remote_version_path = Path::new(&remote_path).join("version.json");
@@ -285,19 +1029,33 @@ async fn rollback_project(
         .map_err(|e| format!("读取版本文件失败: {}", e))?;
 
     // 上传 version.json
-    let mut remote_file = sftp.create(&remote_version_path)
-        .map_err(|e| format!("创建远程版本文件失败: {}", e))?;
-    remote_file.write(&version_content)
-        .map_err(|e| format!("写入远程版本文件失败: {}", e))?;
+    backend.write_file(&remote_version_path, &version_content)?;
 
     // 计算总文件数
     let total_files = count_files(&backup_path)?;
     let mut uploaded_files = 0;
+    let mut skipped_files = 0;
+    let mut manifest = UploadManifest::new();
+    let mut touched_keys = std::collections::HashSet::new();
 
-    // 上传备份文件到服务器
+    // 上传备份文件到服务器（回滚总是全量恢复，不做增量跳过）
     let remote_path = Path::new(&remote_path);
-    upload_dir(&sftp, &backup_path, remote_path, window, total_files, &mut uploaded_files)
-        .map_err(|e| format!("回滚失败: {}", e))?;
+    upload_dir(
+        &mut backend,
+        &backup_path,
+        remote_path,
+        window.clone(),
+        total_files,
+        &mut uploaded_files,
+        &mut skipped_files,
+        &mut manifest,
+        &mut touched_keys,
+        false,
+    )
+    .map_err(|e| format!("回滚失败: {}", e))?;
+
+    // 文件恢复完成后执行后置命令
+    run_command_hooks(&backend, &window, &post_commands, false)?;
 
     Ok(())
 }
@@ -310,41 +1068,169 @@ async fn backup_remote_files(
     username: String,
     password: String,
     remote_path: String,
+    protocol: Option<String>,
+    private_key_path: Option<String>,
+    passphrase: Option<String>,
+    use_agent: Option<bool>,
+    retention: Option<usize>,
 ) -> Result<(), String> {
-    // 连接服务器
-    let host_with_port = if !host.contains(":") {
-        format!("{}:22", host)
-    } else {
-        host.clone()
+    let protocol = protocol.unwrap_or_else(default_protocol);
+    let auth = SshAuth {
+        private_key_path,
+        passphrase,
+        use_agent: use_agent.unwrap_or(false),
     };
+    let mut backend = connect(&protocol, &host, &username, &password, &auth)?;
 
-    let tcp = TcpStream::connect(&host_with_port)
-        .map_err(|e| format!("连接服务器失败: {}", e))?;
-    
-    let mut sess = Session::new()
-        .map_err(|e| format!("创建会话失败: {}", e))?;
-    
-    sess.set_tcp_stream(tcp);
-    sess.set_timeout(60000);
-    sess.handshake()
-        .map_err(|e| format!("握手失败: {}", e))?;
-
-    sess.userauth_password(&username, &password)
-        .map_err(|e| format!("认证失败: {}", e))?;
-
-    let sftp = sess.sftp()
-        .map_err(|e| format!("创建SFTP会话失败: {}", e))?;
-
-    // 获取备份目录
+    // 每次备份落地到独立的时间戳快照目录，而不是覆盖上一次的备份
     let backup_dir = get_backup_dir(&project_name, &env)?;
+    let snapshot_path = reserve_backup_snapshot_path(&backup_dir);
+    let backup_id = snapshot_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
     let remote_path = Path::new(&remote_path);
 
-    // 下载远程文件到本地备份目录
-    download_for_backup(&sftp, remote_path, Path::new(&backup_dir))?;
+    // 下载远程文件到本地备份快照目录
+    download_for_backup(&mut backend, remote_path, &snapshot_path)?;
+    info!("已创建备份快照: {}", backup_id);
+
+    // 按保留数量清理最旧的历史快照
+    if let Some(retention) = retention {
+        prune_backups(&backup_dir, retention)?;
+    }
 
     Ok(())
 }
 
+// 浅克隆一个 Git 仓库到 `get_app_dir()/tmp` 下的临时目录，可选签出指定分支或提交
+fn clone_repo(url: &str, branch: Option<&str>, revision: Option<&str>) -> Result<PathBuf, String> {
+    let clone_id = chrono::Utc::now().format("%Y%m%dT%H%M%S%.f").to_string();
+    let clone_dir = Path::new(&get_app_dir()).join("tmp").join(format!("git-{}", clone_id));
+    if let Some(parent) = clone_dir.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    }
+
+    let mut clone_cmd = std::process::Command::new("git");
+    clone_cmd.arg("clone");
+    // 指定了具体 revision 时无法保证浅克隆能取到该提交，退化为完整克隆
+    if revision.is_none() {
+        clone_cmd.args(["--depth", "1"]);
+        if let Some(branch) = branch {
+            clone_cmd.args(["--branch", branch]);
+        }
+    }
+    clone_cmd.arg(url).arg(&clone_dir);
+
+    info!("正在克隆仓库 {} 到 {}", url, clone_dir.display());
+    let output = clone_cmd.output().map_err(|e| format!("执行git clone失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git clone失败: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    if let Some(revision) = revision {
+        let checkout_output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&clone_dir)
+            .arg("checkout")
+            .arg(revision)
+            .output()
+            .map_err(|e| format!("执行git checkout失败: {}", e))?;
+        if !checkout_output.status.success() {
+            return Err(format!("git checkout失败: {}", String::from_utf8_lossy(&checkout_output.stderr)));
+        }
+    }
+
+    // 克隆目录只是部署源，.git 元数据既不需要上传，留在目标服务器上也会暴露完整版本历史
+    let git_dir = clone_dir.join(".git");
+    if git_dir.exists() {
+        fs::remove_dir_all(&git_dir)
+            .map_err(|e| format!("清理克隆目录中的.git失败: {}", e))?;
+    }
+
+    Ok(clone_dir)
+}
+
+#[command]
+async fn deploy_from_git(
+    window: tauri::Window,
+    project_name: String,
+    env: String,
+    host: String,
+    username: String,
+    password: String,
+    remote_path: String,
+    protocol: Option<String>,
+    private_key_path: Option<String>,
+    passphrase: Option<String>,
+    use_agent: Option<bool>,
+    incremental: Option<bool>,
+    pre_commands: Option<Vec<String>>,
+    post_commands: Option<Vec<String>>,
+    retention: Option<usize>,
+    url: String,
+    branch: Option<String>,
+    revision: Option<String>,
+) -> Result<(), String> {
+    let protocol = protocol.unwrap_or_else(default_protocol);
+    let branch = branch.filter(|b| !b.is_empty());
+    let revision = revision.filter(|r| !r.is_empty());
+    if branch.is_some() && revision.is_some() {
+        return Err("branch 和 revision 只能指定一个".to_string());
+    }
+
+    let clone_dir = clone_repo(&url, branch.as_deref(), revision.as_deref())?;
+    let clone_path = clone_dir.to_string_lossy().to_string();
+
+    // 部署前先备份远程现状，复用既有备份流程
+    let backup_result = backup_remote_files(
+        project_name.clone(),
+        env.clone(),
+        host.clone(),
+        username.clone(),
+        password.clone(),
+        remote_path.clone(),
+        Some(protocol.clone()),
+        private_key_path.clone(),
+        passphrase.clone(),
+        use_agent,
+        retention,
+    )
+    .await;
+
+    let deploy_result = match backup_result {
+        Ok(()) => {
+            deploy_project(
+                window,
+                project_name,
+                clone_path,
+                env,
+                host,
+                username,
+                password,
+                remote_path,
+                Some(protocol),
+                private_key_path,
+                passphrase,
+                use_agent,
+                incremental,
+                pre_commands,
+                post_commands,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    // 无论部署成功与否都清理克隆出的临时目录
+    if let Err(e) = fs::remove_dir_all(&clone_dir) {
+        warn!("清理Git临时克隆目录失败 {}: {}", clone_dir.display(), e);
+    }
+
+    deploy_result
+}
+
 #[command]
 fn get_app_dir() -> String {
     #[cfg(debug_assertions)]
@@ -364,13 +1250,21 @@ fn get_app_dir() -> String {
 }
 
 fn main() {
+    if let Err(e) = init_logging() {
+        eprintln!("初始化日志系统失败: {}", e);
+    }
+
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             deploy_project,
             rollback_project,
             get_app_dir,
             get_backup_dir,
-            backup_remote_files
+            backup_remote_files,
+            get_log_path,
+            read_recent_logs,
+            list_backups,
+            deploy_from_git
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");